@@ -1,29 +1,61 @@
 use actix_web::{
-    dev::Service as _, web, App, Error, HttpResponse, HttpServer, Responder, ResponseError,
+    dev::{Service, ServiceRequest, ServiceResponse, Transform},
+    web, App, Error, HttpResponse, HttpServer, Responder, ResponseError,
 };
 use async_trait::async_trait;
-use redis::{AsyncCommands, RedisResult};
+use bb8::Pool;
+use bb8_redis::RedisConnectionManager;
+use futures_util::future::LocalBoxFuture;
+use redis::{AsyncCommands, ConnectionAddr, IntoConnectionInfo, RedisResult};
 use serde::{Deserialize, Serialize};
-use std::sync::Mutex;
+use std::collections::HashMap;
+use std::future::{ready, Ready};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use thiserror::Error;
+use tracing::Instrument;
+use uuid::Uuid;
+
+/// Atomically sets `KEYS[1]` to `ARGV[2]` only if its current value is
+/// `ARGV[1]`, so callers get compare-and-set semantics without a
+/// GET-then-SET race under concurrency. The conflict error is prefixed with
+/// the `CASCONFLICT` code (mirroring how Redis itself prefixes errors like
+/// `WRONGTYPE`) so callers can tell "lost the race, retry" apart from a
+/// genuine Redis outage.
+const COMPARE_AND_SET_SCRIPT: &str = r#"
+if redis.call("GET", KEYS[1]) == ARGV[1] then
+    return redis.call("SET", KEYS[1], ARGV[2])
+else
+    return redis.error_reply("CASCONFLICT value mismatch")
+end
+"#;
 
 struct AppState {
-    client: Mutex<Box<dyn RedisAPI>>,
+    client: RedisClient,
 }
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
-    let cli = RedisClient::new("redis://127.0.0.1:6379");
+    tracing_subscriber::fmt::init();
+
+    let cli = RedisClient::new("redis://127.0.0.1:6379", RedisClientConfig::default())
+        .await
+        .map_err(std::io::Error::other)?;
 
-    let shared_data = web::Data::new(AppState {
-        client: Mutex::new(Box::new(cli)),
-    });
+    let shared_data = web::Data::new(AppState { client: cli });
 
     HttpServer::new(move || {
         App::new()
+            .wrap(RequestTracing)
             .app_data(shared_data.clone())
             .route("/", web::get().to(get_value))
             .route("/", web::post().to(set_value))
+            .route("/mget", web::post().to(mget_value))
+            .route("/mset", web::post().to(mset_value))
+            .route("/cas", web::post().to(cas_value))
+            .route("/expire", web::post().to(expire_value))
+            .route("/ttl", web::get().to(ttl_value))
+            .route("/pipeline", web::post().to(pipeline_value))
     })
     .bind("127.0.0.1:8080")?
     .run()
@@ -42,18 +74,56 @@ struct GetValueResp {
 
 #[derive(Error, Debug)]
 enum MyError {
-    #[error("Failed to acquire lock")]
-    LockError,
-    #[error("Failed to retrieve value")]
-    ClientError(#[from] Box<dyn std::error::Error>), // Example of wrapping an error
+    #[error("redis is unavailable: {0}")]
+    RedisUnavailable(redis::RedisError),
+    #[error("key not found")]
+    KeyNotFound,
+    #[error("failed to parse stored value: {0}")]
+    DataParsing(String),
+    #[error("bad request: {0}")]
+    BadRequest(String),
+    #[error("compare-and-set conflict: {0}")]
+    CasConflict(String),
+}
+
+/// Code `redis.error_reply` is given by [`COMPARE_AND_SET_SCRIPT`] when the
+/// current value doesn't match the caller's expected value. redis-rs has no
+/// built-in kind for application-defined Lua errors, so we recognize it by
+/// this code and surface it as a 409 instead of a generic bad request.
+const CAS_CONFLICT_CODE: &str = "CASCONFLICT";
+
+impl From<redis::RedisError> for MyError {
+    /// Classifies a `redis::RedisError` by its `ErrorKind` so the HTTP layer
+    /// can tell a transient outage apart from a malformed stored value.
+    ///
+    /// `ExtensionError`/`ResponseError` cover server-side command and Lua
+    /// errors (e.g. `WRONGTYPE`, a bad arity) that mean "the request was
+    /// malformed", not "Redis is unavailable" - they must not fall into the
+    /// `RedisUnavailable` catch-all.
+    fn from(err: redis::RedisError) -> Self {
+        use redis::ErrorKind::*;
+        match err.kind() {
+            IoError | ClusterDown | MasterDown | TryAgain => MyError::RedisUnavailable(err),
+            TypeError => MyError::DataParsing(err.to_string()),
+            ExtensionError if err.code() == Some(CAS_CONFLICT_CODE) => {
+                MyError::CasConflict(err.to_string())
+            }
+            ExtensionError | ResponseError => MyError::BadRequest(err.to_string()),
+            _ => MyError::RedisUnavailable(err),
+        }
+    }
 }
 
 impl ResponseError for MyError {
     fn error_response(&self) -> HttpResponse {
-        match *self {
-            MyError::LockError => HttpResponse::InternalServerError().json("Internal server error"),
-            MyError::ClientError(ref e) => HttpResponse::InternalServerError()
-                .json(format!("Failed to retrieve value: {:?}", e)),
+        match self {
+            MyError::RedisUnavailable(e) => HttpResponse::ServiceUnavailable()
+                .json(format!("redis is unavailable: {:?}", e)),
+            MyError::KeyNotFound => HttpResponse::NotFound().json("key not found"),
+            MyError::DataParsing(e) => HttpResponse::BadGateway()
+                .json(format!("failed to parse stored value: {}", e)),
+            MyError::BadRequest(e) => HttpResponse::BadRequest().json(e),
+            MyError::CasConflict(e) => HttpResponse::Conflict().json(e),
         }
     }
 }
@@ -62,15 +132,12 @@ async fn get_value(
     req: web::Json<GetValueReq>,
     data: web::Data<AppState>,
 ) -> Result<impl Responder, Error> {
-    let client = data.client.lock().map_err(|e| {
-        eprintln!("Failed to acquire lock: {:?}", e);
-        MyError::LockError
+    let val = data.client.get(&req.key).await.map_err(|e| {
+        tracing::error!(error = ?e, "failed to retrieve value");
+        MyError::from(e)
     })?;
 
-    let val = client.get(&req.key).await.map_err(|e| {
-        eprintln!("Failed to retrieve value: {:?}", e);
-        MyError::ClientError(Box::new(e))
-    })?;
+    let val = val.ok_or(MyError::KeyNotFound)?;
 
     Ok(HttpResponse::Ok().json(GetValueResp {
         key: req.key.clone(),
@@ -82,54 +149,506 @@ async fn get_value(
 struct SetValueReq {
     key: String,
     value: String,
+    #[serde(default)]
+    ttl_secs: Option<u64>,
 }
 
 async fn set_value(
     req: web::Json<SetValueReq>,
     data: web::Data<AppState>,
 ) -> Result<impl Responder, Error> {
-    let client = data.client.lock().map_err(|e| {
-        eprintln!("Failed to acquire lock: {:?}", e);
-        MyError::LockError
+    match req.ttl_secs {
+        Some(ttl_secs) => data.client.set_ex(&req.key, &req.value, ttl_secs).await,
+        None => data.client.set(&req.key, &req.value).await,
+    }
+    .map_err(|e| {
+        tracing::error!(error = ?e, "failed to set value");
+        MyError::from(e)
+    })?;
+
+    Ok(HttpResponse::Ok().json(()))
+}
+
+#[derive(Serialize, Deserialize)]
+struct MgetReq {
+    keys: Vec<String>,
+}
+#[derive(Serialize, Deserialize)]
+struct MgetResp {
+    values: Vec<Option<String>>,
+}
+
+async fn mget_value(
+    req: web::Json<MgetReq>,
+    data: web::Data<AppState>,
+) -> Result<impl Responder, Error> {
+    if req.keys.is_empty() {
+        return Err(MyError::BadRequest("keys must not be empty".to_string()).into());
+    }
+
+    let values = data.client.mget(&req.keys).await.map_err(|e| {
+        tracing::error!(error = ?e, "failed to retrieve values");
+        MyError::from(e)
     })?;
 
-    let res = client.set(&req.key, &req.value).await.map_err(|e| {
-        eprintln!("Failed to set value: {:?}", e);
-        MyError::ClientError(Box::new(e))
+    Ok(HttpResponse::Ok().json(MgetResp { values }))
+}
+
+#[derive(Serialize, Deserialize)]
+struct KeyValuePair {
+    key: String,
+    value: String,
+}
+#[derive(Serialize, Deserialize)]
+struct MsetReq {
+    pairs: Vec<KeyValuePair>,
+}
+
+async fn mset_value(
+    req: web::Json<MsetReq>,
+    data: web::Data<AppState>,
+) -> Result<impl Responder, Error> {
+    if req.pairs.is_empty() {
+        return Err(MyError::BadRequest("pairs must not be empty".to_string()).into());
+    }
+
+    let pairs: Vec<(String, String)> = req
+        .pairs
+        .iter()
+        .map(|kv| (kv.key.clone(), kv.value.clone()))
+        .collect();
+
+    data.client.mset(&pairs).await.map_err(|e| {
+        tracing::error!(error = ?e, "failed to set values");
+        MyError::from(e)
     })?;
 
+    Ok(HttpResponse::Ok().json(()))
+}
+
+/// Name the compare-and-set script is registered under in
+/// [`script_registry`].
+const COMPARE_AND_SET_SCRIPT_NAME: &str = "compare_and_set";
+
+#[derive(Serialize, Deserialize)]
+struct CasReq {
+    key: String,
+    expected: String,
+    value: String,
+}
+
+async fn cas_value(
+    req: web::Json<CasReq>,
+    data: web::Data<AppState>,
+) -> Result<impl Responder, Error> {
+    let res = data
+        .client
+        .eval_script(
+            COMPARE_AND_SET_SCRIPT_NAME,
+            std::slice::from_ref(&req.key),
+            &[req.expected.clone(), req.value.clone()],
+        )
+        .await
+        .map_err(|e| {
+            tracing::error!(error = ?e, "failed to apply compare-and-set");
+            MyError::from(e)
+        })?;
+
     Ok(HttpResponse::Ok().json(res))
 }
 
+#[derive(Serialize, Deserialize)]
+struct ExpireReq {
+    key: String,
+    ttl_secs: i64,
+}
+#[derive(Serialize, Deserialize)]
+struct ExpireResp {
+    did_set: bool,
+}
+
+async fn expire_value(
+    req: web::Json<ExpireReq>,
+    data: web::Data<AppState>,
+) -> Result<impl Responder, Error> {
+    let did_set = data.client.expire(&req.key, req.ttl_secs).await.map_err(|e| {
+        tracing::error!(error = ?e, "failed to set expiry");
+        MyError::from(e)
+    })?;
+
+    Ok(HttpResponse::Ok().json(ExpireResp { did_set }))
+}
+
+#[derive(Serialize, Deserialize)]
+struct TtlReq {
+    key: String,
+}
+#[derive(Serialize, Deserialize)]
+struct TtlResp {
+    ttl_secs: i64,
+}
+
+async fn ttl_value(
+    req: web::Json<TtlReq>,
+    data: web::Data<AppState>,
+) -> Result<impl Responder, Error> {
+    let ttl_secs = data.client.ttl(&req.key).await.map_err(|e| {
+        tracing::error!(error = ?e, "failed to retrieve ttl");
+        MyError::from(e)
+    })?;
+
+    Ok(HttpResponse::Ok().json(TtlResp { ttl_secs }))
+}
+
+#[derive(Serialize, Deserialize)]
+struct PipelineCommand {
+    cmd: String,
+    #[serde(default)]
+    args: Vec<String>,
+}
+#[derive(Serialize, Deserialize)]
+struct PipelineReq {
+    commands: Vec<PipelineCommand>,
+}
+#[derive(Serialize, Deserialize)]
+struct PipelineResp {
+    results: Vec<String>,
+}
+
+/// Data commands this route allows through `/pipeline`. Keeps the route in
+/// line with every other one in this service, which exposes a narrow set of
+/// key/value operations rather than passing arbitrary commands (e.g.
+/// `FLUSHALL`, `CONFIG`, `SHUTDOWN`) straight through to Redis.
+const ALLOWED_PIPELINE_COMMANDS: &[&str] = &[
+    "GET", "SET", "SETEX", "MGET", "MSET", "EXPIRE", "TTL", "DEL", "EXISTS",
+];
+
+async fn pipeline_value(
+    req: web::Json<PipelineReq>,
+    data: web::Data<AppState>,
+) -> Result<impl Responder, Error> {
+    if req.commands.is_empty() {
+        return Err(MyError::BadRequest("commands must not be empty".to_string()).into());
+    }
+
+    for command in &req.commands {
+        if !ALLOWED_PIPELINE_COMMANDS.contains(&command.cmd.to_ascii_uppercase().as_str()) {
+            return Err(MyError::BadRequest(format!(
+                "command `{}` is not allowed in a pipeline, expected one of {ALLOWED_PIPELINE_COMMANDS:?}",
+                command.cmd
+            ))
+            .into());
+        }
+    }
+
+    let commands: Vec<(String, Vec<String>)> = req
+        .commands
+        .iter()
+        .map(|c| (c.cmd.clone(), c.args.clone()))
+        .collect();
+
+    let values = data.client.pipeline(&commands).await.map_err(|e| {
+        tracing::error!(error = ?e, "failed to run pipeline");
+        MyError::from(e)
+    })?;
+
+    let results = values.iter().map(redis_value_to_string).collect();
+
+    Ok(HttpResponse::Ok().json(PipelineResp { results }))
+}
+
+/// Renders a `redis::Value` as a human-readable string for JSON responses,
+/// since `redis::Value` itself doesn't implement `Serialize`.
+fn redis_value_to_string(value: &redis::Value) -> String {
+    match value {
+        redis::Value::Nil => "nil".to_string(),
+        redis::Value::Int(i) => i.to_string(),
+        redis::Value::Data(bytes) => String::from_utf8_lossy(bytes).into_owned(),
+        redis::Value::Bulk(values) => values
+            .iter()
+            .map(redis_value_to_string)
+            .collect::<Vec<_>>()
+            .join(","),
+        redis::Value::Status(s) => s.clone(),
+        redis::Value::Okay => "OK".to_string(),
+    }
+}
+
+/// Knobs for the underlying `bb8` connection pool. Mirrors the subset of
+/// `bb8::Builder` settings that matter for a Redis-backed service.
+pub struct RedisClientConfig {
+    pub max_size: u32,
+    pub min_idle: Option<u32>,
+    pub connection_timeout: Duration,
+    /// Skips TLS certificate verification for `rediss://` urls. Only takes
+    /// effect when `redis_url` uses the `rediss://` scheme; ignored
+    /// otherwise. Intended for connecting to a server with a self-signed
+    /// certificate (e.g. local development) - never enable this against a
+    /// production endpoint.
+    pub tls_insecure: bool,
+}
+
+impl Default for RedisClientConfig {
+    fn default() -> Self {
+        RedisClientConfig {
+            max_size: 10,
+            min_idle: Some(1),
+            connection_timeout: Duration::from_secs(5),
+            tls_insecure: false,
+        }
+    }
+}
+
+#[derive(Clone)]
 pub struct RedisClient {
-    client: redis::Client,
+    pool: Pool<RedisConnectionManager>,
+    scripts: Arc<HashMap<String, redis::Script>>,
+}
+
+/// URL schemes the `redis` crate recognizes: plain TCP, TLS, and Unix
+/// socket (in both its `unix://` and `redis+unix://` spellings).
+const SUPPORTED_SCHEMES: &[&str] = &["redis://", "rediss://", "redis+unix://", "unix://"];
+
+#[derive(Error, Debug)]
+pub enum RedisClientError {
+    #[error("unsupported redis url scheme in `{0}`, expected one of {SUPPORTED_SCHEMES:?}")]
+    UnsupportedScheme(String),
+    #[error("failed to build redis connection manager")]
+    Manager(#[from] redis::RedisError),
+    #[error("failed to build redis connection pool")]
+    Pool(#[from] bb8::RunError<redis::RedisError>),
 }
 
 impl RedisClient {
-    pub fn new(redis_url: &str) -> RedisClient {
-        RedisClient {
-            client: redis::Client::open(redis_url).unwrap(),
+    pub async fn new(
+        redis_url: &str,
+        config: RedisClientConfig,
+    ) -> Result<RedisClient, RedisClientError> {
+        if !SUPPORTED_SCHEMES.iter().any(|scheme| redis_url.starts_with(scheme)) {
+            return Err(RedisClientError::UnsupportedScheme(redis_url.to_string()));
         }
+
+        let mut connection_info = redis_url.into_connection_info()?;
+        if config.tls_insecure {
+            if let ConnectionAddr::TcpTls { insecure, .. } = &mut connection_info.addr {
+                *insecure = true;
+            }
+        }
+
+        let manager = RedisConnectionManager::new(connection_info)?;
+        let pool = Pool::builder()
+            .max_size(config.max_size)
+            .min_idle(config.min_idle)
+            .connection_timeout(config.connection_timeout)
+            .build(manager)
+            .await?;
+
+        Ok(RedisClient {
+            pool,
+            scripts: Arc::new(script_registry()),
+        })
     }
 }
 
+/// Scripts registered at startup, keyed by the name callers pass to
+/// `RedisAPI::eval_script`.
+fn script_registry() -> HashMap<String, redis::Script> {
+    let mut scripts = HashMap::new();
+    scripts.insert(
+        COMPARE_AND_SET_SCRIPT_NAME.to_string(),
+        redis::Script::new(COMPARE_AND_SET_SCRIPT),
+    );
+    scripts
+}
+
 #[async_trait]
 impl RedisAPI for RedisClient {
-    async fn get(&self, key: &str) -> RedisResult<String> {
-        let mut conn = self.client.get_multiplexed_async_connection().await?;
-        let value: String = conn.get(key).await?;
+    async fn get(&self, key: &str) -> RedisResult<Option<String>> {
+        let mut conn = self.pool.get().await.map_err(bb8_run_error_to_redis_error)?;
+        let value: Option<String> = conn.get(key).await?;
         RedisResult::Ok(value)
     }
 
     async fn set(&self, key: &str, value: &str) -> RedisResult<()> {
-        let mut conn = self.client.get_multiplexed_async_connection().await?;
-        conn.set(key, value).await?;
+        let mut conn = self.pool.get().await.map_err(bb8_run_error_to_redis_error)?;
+        conn.set::<_, _, ()>(key, value).await?;
+        RedisResult::Ok(())
+    }
+
+    async fn set_ex(&self, key: &str, value: &str, ttl_secs: u64) -> RedisResult<()> {
+        let mut conn = self.pool.get().await.map_err(bb8_run_error_to_redis_error)?;
+        conn.set_ex::<_, _, ()>(key, value, ttl_secs).await?;
         RedisResult::Ok(())
     }
+
+    async fn expire(&self, key: &str, ttl_secs: i64) -> RedisResult<bool> {
+        let mut conn = self.pool.get().await.map_err(bb8_run_error_to_redis_error)?;
+        let did_set: bool = conn.expire(key, ttl_secs).await?;
+        RedisResult::Ok(did_set)
+    }
+
+    async fn ttl(&self, key: &str) -> RedisResult<i64> {
+        let mut conn = self.pool.get().await.map_err(bb8_run_error_to_redis_error)?;
+        let seconds_remaining: i64 = conn.ttl(key).await?;
+        RedisResult::Ok(seconds_remaining)
+    }
+
+    async fn mget(&self, keys: &[String]) -> RedisResult<Vec<Option<String>>> {
+        let mut conn = self.pool.get().await.map_err(bb8_run_error_to_redis_error)?;
+        let values: Vec<Option<String>> = conn.mget(keys).await?;
+        RedisResult::Ok(values)
+    }
+
+    async fn mset(&self, pairs: &[(String, String)]) -> RedisResult<()> {
+        let mut conn = self.pool.get().await.map_err(bb8_run_error_to_redis_error)?;
+        conn.mset::<_, _, ()>(pairs).await?;
+        RedisResult::Ok(())
+    }
+
+    async fn pipeline(&self, commands: &[(String, Vec<String>)]) -> RedisResult<Vec<redis::Value>> {
+        let mut conn = self.pool.get().await.map_err(bb8_run_error_to_redis_error)?;
+        let mut pipe = redis::pipe();
+        for (cmd, args) in commands {
+            pipe.cmd(cmd).arg(args);
+        }
+        pipe.query_async(&mut *conn).await
+    }
+
+    async fn eval_script(&self, name: &str, keys: &[String], args: &[String]) -> RedisResult<String> {
+        let script = self.scripts.get(name).ok_or_else(|| {
+            redis::RedisError::from((redis::ErrorKind::ClientError, "no script registered with name", name.to_string()))
+        })?;
+
+        let mut conn = self.pool.get().await.map_err(bb8_run_error_to_redis_error)?;
+        script
+            .key(keys)
+            .arg(args)
+            .invoke_async(&mut *conn)
+            .await
+    }
+}
+
+/// `bb8::Pool::get` returns `bb8::RunError<redis::RedisError>`, but the
+/// `RedisAPI` trait speaks in `redis::RedisResult` so callers only deal
+/// with one error type. Timeouts are mapped to an IO error so they are
+/// still distinguishable from a bad command.
+fn bb8_run_error_to_redis_error(err: bb8::RunError<redis::RedisError>) -> redis::RedisError {
+    match err {
+        bb8::RunError::User(e) => e,
+        bb8::RunError::TimedOut => redis::RedisError::from((
+            redis::ErrorKind::IoError,
+            "timed out waiting for a connection from the pool",
+        )),
+    }
 }
 
 #[async_trait]
 pub trait RedisAPI: Send + Sync {
-    async fn get(&self, key: &str) -> RedisResult<String>;
+    async fn get(&self, key: &str) -> RedisResult<Option<String>>;
     async fn set(&self, key: &str, value: &str) -> RedisResult<()>;
+    /// Sets `key` to `value` with an expiry, in one round-trip via `SETEX`.
+    async fn set_ex(&self, key: &str, value: &str, ttl_secs: u64) -> RedisResult<()>;
+    /// Sets a TTL on an existing key via `EXPIRE`. Returns whether the
+    /// timeout was set (`false` if the key doesn't exist).
+    async fn expire(&self, key: &str, ttl_secs: i64) -> RedisResult<bool>;
+    /// Returns the remaining TTL in seconds via `TTL`, or a negative value
+    /// per Redis semantics if the key has no expiry or doesn't exist.
+    async fn ttl(&self, key: &str) -> RedisResult<i64>;
+    async fn mget(&self, keys: &[String]) -> RedisResult<Vec<Option<String>>>;
+    async fn mset(&self, pairs: &[(String, String)]) -> RedisResult<()>;
+    /// Issues a batch of arbitrary commands in a single round-trip via
+    /// `redis::pipe()`. Each entry is a command name paired with its args.
+    async fn pipeline(&self, commands: &[(String, Vec<String>)]) -> RedisResult<Vec<redis::Value>>;
+    /// Runs a registered Lua script by name via `EVALSHA`, falling back to
+    /// `EVAL` automatically when the server hasn't cached it yet. Gives
+    /// callers atomic compare-and-set semantics a plain GET/SET can't.
+    async fn eval_script(&self, name: &str, keys: &[String], args: &[String]) -> RedisResult<String>;
+}
+
+/// HTTP header carrying the per-request id generated by [`RequestTracing`],
+/// echoed back so a response (including an error one) can be traced back to
+/// its log entry.
+const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Access-log and request-tracing middleware. Wraps every request in a
+/// `tracing` span tagged with a generated request id, method and path, logs
+/// the outcome with its status and elapsed duration, and echoes the request
+/// id back on the response so a 503 can be correlated with a log entry.
+pub struct RequestTracing;
+
+impl<S, B> Transform<S, ServiceRequest> for RequestTracing
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = RequestTracingMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RequestTracingMiddleware { service }))
+    }
+}
+
+pub struct RequestTracingMiddleware<S> {
+    service: S,
+}
+
+impl<S, B> Service<ServiceRequest> for RequestTracingMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    actix_web::dev::forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let request_id = Uuid::new_v4();
+        let method = req.method().clone();
+        let path = req.path().to_string();
+        let remote_addr = req
+            .connection_info()
+            .realip_remote_addr()
+            .unwrap_or("unknown")
+            .to_string();
+
+        let span = tracing::info_span!(
+            "http_request",
+            %request_id,
+            %method,
+            %path,
+            %remote_addr,
+            status = tracing::field::Empty,
+            elapsed_ms = tracing::field::Empty,
+        );
+
+        let started_at = Instant::now();
+        let fut = self.service.call(req);
+
+        Box::pin(
+            async move {
+                let mut res = fut.await?;
+                let elapsed_ms = started_at.elapsed().as_millis();
+
+                res.response_mut().headers_mut().insert(
+                    actix_web::http::header::HeaderName::from_static(REQUEST_ID_HEADER),
+                    actix_web::http::header::HeaderValue::from_str(&request_id.to_string())
+                        .expect("uuid string is a valid header value"),
+                );
+
+                let span = tracing::Span::current();
+                span.record("status", res.status().as_u16());
+                span.record("elapsed_ms", elapsed_ms);
+                tracing::info!("request completed");
+
+                Ok(res)
+            }
+            .instrument(span),
+        )
+    }
 }